@@ -0,0 +1,193 @@
+//! Build Id PAK archives from files and directories.
+
+use crate::errors::IdPakLoadError;
+use crate::fileentry::{FILE_ENTRY_SIZE, NAME_LEN};
+use crate::header::{PAK_SIG, HEADER_SIZE};
+use crate::pak::IdPakLoadResult;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Builds a valid Quake PAK archive by appending files and
+/// directories, then writing out the file table and header.
+///
+/// Mirrors the ergonomics of `tar::Builder`: append entries one
+/// at a time, then call [`IdPakBuilder::finish`] to patch the
+/// header and write the table of contents.
+pub struct IdPakBuilder<W: Write + Seek> {
+    /// Destination the archive is written to.
+    writer: W,
+
+    /// Recorded entries as `(name, offset, size)`, in append order.
+    entries: Vec<(String, u32, u32)>,
+
+    /// Current write position, used as the offset of the next
+    /// appended file.
+    cursor: u32,
+}
+
+impl<W: Write + Seek> IdPakBuilder<W> {
+    /// Create a new builder, writing a placeholder header to `writer`
+    /// that is patched once [`IdPakBuilder::finish`] is called.
+    pub fn new(mut writer: W) -> IdPakLoadResult<Self> {
+        writer
+            .write_all(&[0u8; HEADER_SIZE as usize])
+            .map_err(IdPakLoadError::Io)?;
+
+        Ok(IdPakBuilder {
+            writer,
+            entries: Vec::new(),
+            cursor: HEADER_SIZE,
+        })
+    }
+
+    /// Append a file's contents from an in-memory byte slice.
+    pub fn append_bytes(&mut self, path: &str, data: &[u8]) -> IdPakLoadResult<()> {
+        check_name(path)?;
+
+        self.writer
+            .write_all(data)
+            .map_err(IdPakLoadError::Io)?;
+
+        self.push_entry(path, data.len() as u32)
+    }
+
+    /// Append a file's contents, streaming from anything that implements `Read`.
+    pub fn append_file(&mut self, path: &str, file: &mut impl Read) -> IdPakLoadResult<()> {
+        check_name(path)?;
+
+        let written = std::io::copy(file, &mut self.writer).map_err(IdPakLoadError::Io)?;
+
+        self.push_entry(path, written as u32)
+    }
+
+    /// Recursively append every file under `base`, using forward-slash
+    /// relative paths as entry names.
+    pub fn append_dir_all(&mut self, base: &Path) -> IdPakLoadResult<()> {
+        self.append_dir(base, base)
+    }
+
+    /// Finish writing the archive: write the file table after the last
+    /// appended file, then seek back and patch the header to point at it.
+    pub fn finish(mut self) -> IdPakLoadResult<()> {
+        let table_offset = self.cursor;
+
+        for (name, offset, size) in &self.entries {
+            let mut name_bytes = [0u8; NAME_LEN];
+            name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+
+            self.writer
+                .write_all(&name_bytes)
+                .map_err(IdPakLoadError::Io)?;
+            self.writer
+                .write_all(&offset.to_le_bytes())
+                .map_err(IdPakLoadError::Io)?;
+            self.writer
+                .write_all(&size.to_le_bytes())
+                .map_err(IdPakLoadError::Io)?;
+        }
+
+        let table_size = self.entries.len() as u32 * FILE_ENTRY_SIZE;
+
+        self.writer
+            .seek(SeekFrom::Start(0))
+            .map_err(IdPakLoadError::Io)?;
+        self.writer
+            .write_all(&PAK_SIG.map(|c| c as u8))
+            .map_err(IdPakLoadError::Io)?;
+        self.writer
+            .write_all(&table_offset.to_le_bytes())
+            .map_err(IdPakLoadError::Io)?;
+        self.writer
+            .write_all(&table_size.to_le_bytes())
+            .map_err(IdPakLoadError::Io)?;
+
+        Ok(())
+    }
+
+    /// Walk `dir`, appending every file found, recursing into
+    /// subdirectories, with entry names relative to `base`.
+    fn append_dir(&mut self, base: &Path, dir: &Path) -> IdPakLoadResult<()> {
+        let entries = std::fs::read_dir(dir).map_err(IdPakLoadError::Io)?;
+
+        for entry in entries {
+            let entry = entry.map_err(IdPakLoadError::Io)?;
+            let path = entry.path();
+
+            if path.is_dir() {
+                self.append_dir(base, &path)?;
+                continue;
+            }
+
+            let relative = path
+                .strip_prefix(base)
+                .expect("walked path is always under base");
+            let name: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            let name = name.join("/");
+
+            let mut file = File::open(&path).map_err(IdPakLoadError::Io)?;
+            self.append_file(&name, &mut file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Record a just-written entry and advance the write cursor.
+    fn push_entry(&mut self, path: &str, size: u32) -> IdPakLoadResult<()> {
+        self.entries.push((path.to_string(), self.cursor, size));
+        self.cursor += size;
+        Ok(())
+    }
+}
+
+/// Validate that `path` fits the format's 56 byte, null-terminated name field.
+fn check_name(path: &str) -> IdPakLoadResult<()> {
+    if path.len() >= NAME_LEN {
+        return Err(IdPakLoadError::NameTooLong(path.to_string()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pak::{IdPak, IdPakReader};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_builder_round_trip() {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut builder = IdPakBuilder::new(&mut buffer).unwrap();
+
+        builder.append_bytes("sound/items/r_item1.wav", &[1, 2, 3]).unwrap();
+        builder
+            .append_file("maps/e1m1.bsp", &mut Cursor::new(vec![4, 5, 6, 7]))
+            .unwrap();
+        builder.finish().unwrap();
+
+        buffer.set_position(0);
+        let mut pak = IdPak::new(buffer).unwrap();
+        assert_eq!(pak.get_file_count(), 2);
+        assert_eq!(
+            pak.read_file("sound/items/r_item1.wav").unwrap(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(pak.read_file("maps/e1m1.bsp").unwrap(), vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_builder_name_too_long() {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut builder = IdPakBuilder::new(&mut buffer).unwrap();
+        let name = "a".repeat(NAME_LEN);
+
+        match builder.append_bytes(&name, &[0]) {
+            Err(IdPakLoadError::NameTooLong(got)) => assert_eq!(got, name),
+            _ => panic!("Test not passed!"),
+        }
+    }
+}
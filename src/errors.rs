@@ -1,15 +1,109 @@
 //! Errors specific to _id-pak_.
 
-/// Errors encountered when loading and parsing PAK data
+use std::fmt;
+
+/// Errors encountered when loading, validating,
+/// reading, or writing PAK data.
 #[derive(Debug)]
 pub enum IdPakLoadError {
-    /// Encountered when the PAK
-    /// data fails to load, e.g. the path
-    /// is incorrect or there aren't sufficient permissions.
-    FileOpenFailure(std::io::Error),
-
-    /// An error was encountered when
-    /// attempting to read the file headers
-    /// or it's table of contents.
-    UpdateFailure,
+    /// An I/O error was encountered while opening,
+    /// reading, or writing PAK data.
+    Io(std::io::Error),
+
+    /// The PAK header failed to parse, e.g. an invalid
+    /// signature or a truncated buffer.
+    HeaderParse(&'static str),
+
+    /// A file entry in the table of contents failed to parse.
+    EntryParse {
+        /// Position of the entry within the file table.
+        index: u32,
+        /// Why parsing failed.
+        reason: &'static str,
+    },
+
+    /// No entry with the given path exists in the PAK's file table.
+    FileNotFound(String),
+
+    /// A path passed to the builder doesn't fit the format's
+    /// 56 byte, null-terminated name field (55 bytes at most).
+    NameTooLong(String),
+
+    /// An entry's name would escape the extraction destination,
+    /// e.g. via `..` components or an absolute path.
+    UnsafeEntryName(String),
+
+    /// An entry's `[offset, offset + size)` region lies outside
+    /// the archive, or overlaps the header or file table.
+    EntryOutOfBounds {
+        /// The offending entry's name.
+        name: String,
+        /// The entry's recorded offset.
+        offset: u32,
+        /// The entry's recorded size.
+        size: u32,
+    },
+
+    /// Two entries' byte regions overlap.
+    OverlappingEntries {
+        /// Name of the first entry.
+        a: String,
+        /// Name of the second, overlapping entry.
+        b: String,
+    },
+
+    /// Parallel extraction was requested on a PAK whose data source
+    /// can't be reopened and shared across threads.
+    ParallelUnsupported,
+
+    /// A file's computed checksum didn't match the one recorded
+    /// in the manifest passed to `verify_against`.
+    ChecksumMismatch(String),
+}
+
+impl fmt::Display for IdPakLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdPakLoadError::Io(err) => write!(f, "I/O error: {}", err),
+            IdPakLoadError::HeaderParse(reason) => {
+                write!(f, "failed to parse PAK header: {}", reason)
+            }
+            IdPakLoadError::EntryParse { index, reason } => {
+                write!(f, "failed to parse file entry {}: {}", index, reason)
+            }
+            IdPakLoadError::FileNotFound(path) => write!(f, "no such file in PAK: {}", path),
+            IdPakLoadError::NameTooLong(path) => {
+                write!(f, "entry name longer than 55 bytes: {}", path)
+            }
+            IdPakLoadError::UnsafeEntryName(path) => {
+                write!(f, "entry name would escape destination: {}", path)
+            }
+            IdPakLoadError::EntryOutOfBounds { name, offset, size } => write!(
+                f,
+                "entry '{}' [{}, {}) lies outside the archive or overlaps the header or file table",
+                name,
+                offset,
+                *offset as u64 + *size as u64
+            ),
+            IdPakLoadError::OverlappingEntries { a, b } => {
+                write!(f, "entries '{}' and '{}' overlap", a, b)
+            }
+            IdPakLoadError::ParallelUnsupported => write!(
+                f,
+                "parallel extraction requires a PAK opened from a path"
+            ),
+            IdPakLoadError::ChecksumMismatch(path) => {
+                write!(f, "checksum mismatch for: {}", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IdPakLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IdPakLoadError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
 }
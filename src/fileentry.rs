@@ -8,7 +8,7 @@ use std::os::raw::c_char;
 pub const FILE_ENTRY_SIZE: u32 = size_of::<IdPakFileEntry>() as u32;
 
 /// PAK path len
-const NAME_LEN: usize = 56;
+pub(crate) const NAME_LEN: usize = 56;
 
 /// PAK file entry
 #[repr(C)]
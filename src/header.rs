@@ -5,7 +5,7 @@ use std::mem::size_of;
 use std::os::raw::c_char;
 
 /// PAK file signature - "PACK".
-const PAK_SIG: [c_char; 4] = [0x50, 0x41, 0x43, 0x4B];
+pub(crate) const PAK_SIG: [c_char; 4] = [0x50, 0x41, 0x43, 0x4B];
 
 /// PAK header size in bytes.
 pub const HEADER_SIZE: u32 = size_of::<IdPakHeader>() as u32;
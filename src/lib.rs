@@ -1,8 +1,10 @@
 //! Read and parse Id PAK data from files or any other source.
 
+mod builder;
 mod fileentry;
 mod header;
 mod pak;
 
 pub mod errors;
-pub use crate::pak::{open, IdPak, IdPakLoadResult, IdPakReader};
+pub use crate::builder::IdPakBuilder;
+pub use crate::pak::{open, IdPak, IdPakLoadResult, IdPakReader, PakStreamReader};
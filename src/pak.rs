@@ -1,18 +1,102 @@
 use crate::errors::IdPakLoadError;
 use crate::fileentry::{IdPakFileEntry, FILE_ENTRY_SIZE};
 use crate::header::{IdPakHeader, HEADER_SIZE};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub type IdPakLoadResult<T> = Result<T, IdPakLoadError>;
 pub type IdPakFile = IdPak<File>;
 
-pub trait IdPakReader {
-    fn get_file_bytes(&self, path: &str) -> [u8];
-    fn get_file(&self, path: &str) -> File;
+pub trait IdPakReader<R: Read + Seek> {
+    /// Open a bounded reader over a single file stored in the PAK.
+    fn open_file(&mut self, path: &str) -> IdPakLoadResult<PakStreamReader<'_, R>>;
+
+    /// Read the full contents of a file stored in the PAK into a `Vec<u8>`.
+    fn read_file(&mut self, path: &str) -> IdPakLoadResult<Vec<u8>>;
+}
+
+/// A bounded reader over a single file's region within a PAK's data source.
+///
+/// Reads are clamped to the entry's `[base_offset, base_offset + size)` region,
+/// so callers can never read past the file's contents into the next entry.
+/// Seeks are relative to the entry's start and are not clamped: a position at
+/// or past the end of the file reads as end of file.
+pub struct PakStreamReader<'a, R: Read + Seek> {
+    /// The underlying reader over the PAK data.
+    reader: &'a mut BufReader<R>,
+
+    /// Absolute offset of the start of this file within the PAK data.
+    base_offset: u64,
+
+    /// Size of this file in bytes.
+    size: u64,
+
+    /// Current read position, relative to `base_offset`.
+    pos: u64,
+}
+
+impl<'a, R: Read + Seek> PakStreamReader<'a, R> {
+    fn new(reader: &'a mut BufReader<R>, base_offset: u64, size: u64) -> Self {
+        PakStreamReader {
+            reader,
+            base_offset,
+            size,
+            pos: 0,
+        }
+    }
+
+    /// Size of the underlying file, in bytes.
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// Whether the underlying file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+impl<'a, R: Read + Seek> Read for PakStreamReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos >= self.size {
+            return Ok(0);
+        }
+
+        self.reader
+            .seek(SeekFrom::Start(self.base_offset + self.pos))?;
+
+        let remaining = (self.size - self.pos) as usize;
+        let len = buf.len().min(remaining);
+        let read = self.reader.read(&mut buf[..len])?;
+        self.pos += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl<'a, R: Read + Seek> Seek for PakStreamReader<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_pos: i64 = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::Current(offset) => self.pos as i64 + offset,
+            SeekFrom::End(offset) => self.size as i64 + offset,
+        };
+
+        if new_pos < 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            ));
+        }
+
+        self.pos = new_pos as u64;
+        Ok(self.pos)
+    }
 }
 
 /// Representation of an Id PAK file
@@ -25,26 +109,65 @@ pub struct IdPak<R: Read + Seek> {
 
     /// File index
     files: BTreeMap<String, IdPakFileEntry>,
+
+    /// Path the PAK was opened from, if any. Used to reopen the
+    /// source once per entry for parallel extraction.
+    path: Option<PathBuf>,
 }
 
 impl IdPak<File> {
     /// Load PAK data from a file
     pub fn from_path<P: AsRef<Path>>(path: P) -> IdPakLoadResult<IdPakFile> {
-        let file: File = match File::open(&path) {
-            Ok(f) => f,
-            Err(why) => return Err(IdPakLoadError::FileOpenFailure(why)),
-        };
+        let file: File = File::open(&path).map_err(IdPakLoadError::Io)?;
 
         let mut pak: IdPak<File> = IdPak {
             reader: BufReader::new(file),
             header: IdPakHeader::default(),
             files: BTreeMap::new(),
+            path: Some(path.as_ref().to_path_buf()),
         };
 
         pak.update()?;
 
         Ok(pak)
     }
+
+    /// Extract every entry into `dest` using a work-stealing thread
+    /// pool, the way the parallel unpacking of large archives pays
+    /// off most. Since a `BufReader`/`Seek` can't be shared across
+    /// threads, the source file is reopened for each entry.
+    pub fn unpack_parallel<P: AsRef<Path>>(&self, dest: P) -> IdPakLoadResult<()> {
+        let dest = dest.as_ref();
+        let source_path = self
+            .path
+            .clone()
+            .ok_or(IdPakLoadError::ParallelUnsupported)?;
+
+        self.files
+            .iter()
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .try_for_each(|(name, entry)| -> IdPakLoadResult<()> {
+                if !is_safe_entry_name(name) {
+                    return Err(IdPakLoadError::UnsafeEntryName(name.clone()));
+                }
+
+                let file = File::open(&source_path).map_err(IdPakLoadError::Io)?;
+                let mut buffered = BufReader::new(file);
+                let mut reader =
+                    PakStreamReader::new(&mut buffered, entry.offset.into(), entry.size.into());
+
+                let out_path = dest.join(name);
+                if let Some(parent) = out_path.parent() {
+                    std::fs::create_dir_all(parent).map_err(IdPakLoadError::Io)?;
+                }
+
+                let mut out_file = File::create(&out_path).map_err(IdPakLoadError::Io)?;
+                std::io::copy(&mut reader, &mut out_file).map_err(IdPakLoadError::Io)?;
+
+                Ok(())
+            })
+    }
 }
 
 impl<R> IdPak<R>
@@ -58,6 +181,7 @@ where
             reader: BufReader::new(source),
             header: IdPakHeader::default(),
             files: BTreeMap::new(),
+            path: None,
         };
 
         pak.update()?;
@@ -70,12 +194,57 @@ where
         self.files.len()
     }
 
-    /// Read the header and file entries from the loaded PAK data.
+    /// Iterate over every entry in the PAK's file table,
+    /// without re-parsing the archive.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &IdPakFileEntry)> {
+        self.files.iter().map(|(name, entry)| (name.as_str(), entry))
+    }
+
+    /// Whether the PAK contains an entry with the given path.
+    pub fn contains(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    /// Look up an entry's offset and size by path.
+    pub fn entry(&self, path: &str) -> Option<&IdPakFileEntry> {
+        self.files.get(path)
+    }
+
+    /// Extract every entry into `dest`, creating parent directories
+    /// as needed. Entry names containing `..` components or an
+    /// absolute path are rejected, so extraction cannot escape `dest`.
+    pub fn unpack<P: AsRef<Path>>(&mut self, dest: P) -> IdPakLoadResult<()> {
+        let dest = dest.as_ref();
+        let names: Vec<String> = self.files.keys().cloned().collect();
+
+        for name in names {
+            if !is_safe_entry_name(&name) {
+                return Err(IdPakLoadError::UnsafeEntryName(name));
+            }
+
+            let mut reader = self.open_file(&name)?;
+            let out_path = dest.join(&name);
+
+            if let Some(parent) = out_path.parent() {
+                std::fs::create_dir_all(parent).map_err(IdPakLoadError::Io)?;
+            }
+
+            let mut out_file = File::create(&out_path).map_err(IdPakLoadError::Io)?;
+            std::io::copy(&mut reader, &mut out_file).map_err(IdPakLoadError::Io)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the header and file entries from the loaded PAK data,
+    /// then validate that they describe regions which actually fit
+    /// within the archive.
     fn update(&mut self) -> IdPakLoadResult<()> {
         self.files = BTreeMap::new();
 
         self.update_header()?;
         self.update_file_table()?;
+        self.validate()?;
 
         Ok(())
     }
@@ -83,45 +252,168 @@ where
     /// Read the header from the PAK data.
     fn update_header(&mut self) -> IdPakLoadResult<()> {
         let mut buffer = [0u8; HEADER_SIZE as usize];
-        match self.reader.seek(SeekFrom::Start(0)) {
-            Ok(_) => (),
-            Err(_) => return Err(IdPakLoadError::UpdateFailure),
-        };
+        self.reader
+            .seek(SeekFrom::Start(0))
+            .map_err(IdPakLoadError::Io)?;
+        self.reader
+            .read_exact(buffer.as_mut())
+            .map_err(IdPakLoadError::Io)?;
 
-        match self.reader.read_exact(buffer.as_mut()) {
-            Ok(_) => (),
-            Err(_) => return Err(IdPakLoadError::UpdateFailure),
-        };
-
-        self.header = IdPakHeader::try_from(&buffer[..]).unwrap();
+        self.header = IdPakHeader::try_from(&buffer[..]).map_err(IdPakLoadError::HeaderParse)?;
 
         Ok(())
     }
 
+    /// Look up an entry by path.
+    fn get_entry(&self, path: &str) -> IdPakLoadResult<&IdPakFileEntry> {
+        self.files
+            .get(path)
+            .ok_or_else(|| IdPakLoadError::FileNotFound(path.to_string()))
+    }
+
     /// Read the file entries from the PAK data.
     fn update_file_table(&mut self) -> IdPakLoadResult<()> {
-        match self.reader.seek(SeekFrom::Start(self.header.offset.into())) {
-            Ok(_) => (),
-            Err(_) => return Err(IdPakLoadError::UpdateFailure),
-        };
+        self.reader
+            .seek(SeekFrom::Start(self.header.offset.into()))
+            .map_err(IdPakLoadError::Io)?;
 
-        for _ in 0..(self.header.size / FILE_ENTRY_SIZE) {
+        for index in 0..(self.header.size / FILE_ENTRY_SIZE) {
             let mut buffer: [u8; FILE_ENTRY_SIZE as usize] = [0u8; FILE_ENTRY_SIZE as usize];
-            match self.reader.read_exact(buffer.as_mut()) {
-                Ok(_) => (),
-                Err(_) => return Err(IdPakLoadError::UpdateFailure),
-            };
+            self.reader
+                .read_exact(buffer.as_mut())
+                .map_err(IdPakLoadError::Io)?;
 
-            let file_entry = match IdPakFileEntry::try_from(&buffer[..]) {
-                Ok(entry) => entry,
-                Err(_) => return Err(IdPakLoadError::UpdateFailure),
-            };
+            let file_entry = IdPakFileEntry::try_from(&buffer[..])
+                .map_err(|reason| IdPakLoadError::EntryParse { index, reason })?;
 
             self.files.insert(file_entry.get_name(), file_entry);
         }
 
         Ok(())
     }
+
+    /// Verify that the header and file table describe regions which
+    /// fit within the archive and don't overlap the header, the file
+    /// table, or each other.
+    fn validate(&mut self) -> IdPakLoadResult<()> {
+        let stream_len = self.reader.seek(SeekFrom::End(0)).map_err(IdPakLoadError::Io)?;
+
+        if !self.header.size.is_multiple_of(FILE_ENTRY_SIZE) {
+            return Err(IdPakLoadError::HeaderParse(
+                "file table size is not a multiple of the entry size",
+            ));
+        }
+
+        let table_start = u64::from(self.header.offset);
+        let table_end = table_start
+            .checked_add(u64::from(self.header.size))
+            .filter(|end| *end <= stream_len)
+            .ok_or(IdPakLoadError::HeaderParse(
+                "file table lies outside the archive",
+            ))?;
+
+        let mut regions: Vec<(&str, u64, u64)> = Vec::with_capacity(self.files.len());
+
+        for (name, entry) in self.files.iter() {
+            let start = u64::from(entry.offset);
+            let end = start.checked_add(u64::from(entry.size));
+
+            let in_bounds = matches!(end, Some(end) if end <= stream_len);
+            let outside_header = start >= u64::from(HEADER_SIZE);
+            let outside_table =
+                matches!(end, Some(end) if start >= table_end || end <= table_start);
+
+            if !in_bounds || !outside_header || !outside_table {
+                return Err(IdPakLoadError::EntryOutOfBounds {
+                    name: name.clone(),
+                    offset: entry.offset,
+                    size: entry.size,
+                });
+            }
+            let end = end.unwrap();
+
+            for (other_name, other_start, other_end) in &regions {
+                if start < *other_end && end > *other_start {
+                    return Err(IdPakLoadError::OverlappingEntries {
+                        a: (*other_name).to_string(),
+                        b: name.clone(),
+                    });
+                }
+            }
+
+            regions.push((name.as_str(), start, end));
+        }
+
+        Ok(())
+    }
+
+    /// Compute a SHA-256 digest over each entry's byte region. The
+    /// PAK format itself stores no hashes, so this is the only way
+    /// to detect silent corruption of archived data.
+    pub fn checksums(&mut self) -> IdPakLoadResult<BTreeMap<String, [u8; 32]>> {
+        let names: Vec<String> = self.files.keys().cloned().collect();
+        let mut result = BTreeMap::new();
+
+        for name in names {
+            let mut reader = self.open_file(&name)?;
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut reader, &mut hasher).map_err(IdPakLoadError::Io)?;
+            result.insert(name, hasher.finalize().into());
+        }
+
+        Ok(result)
+    }
+
+    /// Recompute checksums and compare them against `manifest`,
+    /// returning an error naming the first file whose contents
+    /// don't match.
+    pub fn verify_against(&mut self, manifest: &BTreeMap<String, [u8; 32]>) -> IdPakLoadResult<()> {
+        let actual = self.checksums()?;
+
+        for (name, expected) in manifest {
+            match actual.get(name) {
+                Some(sum) if sum == expected => continue,
+                Some(_) => return Err(IdPakLoadError::ChecksumMismatch(name.clone())),
+                None => return Err(IdPakLoadError::FileNotFound(name.clone())),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> IdPakReader<R> for IdPak<R>
+where
+    R: Read + Seek,
+{
+    /// Open a bounded reader over a single file stored in the PAK.
+    fn open_file(&mut self, path: &str) -> IdPakLoadResult<PakStreamReader<'_, R>> {
+        let entry = self.get_entry(path)?;
+        let (offset, size) = (entry.offset, entry.size);
+
+        Ok(PakStreamReader::new(&mut self.reader, offset.into(), size.into()))
+    }
+
+    /// Read the full contents of a file stored in the PAK into a `Vec<u8>`.
+    fn read_file(&mut self, path: &str) -> IdPakLoadResult<Vec<u8>> {
+        let mut reader = self.open_file(path)?;
+        let mut buffer = Vec::with_capacity(reader.len() as usize);
+        reader
+            .read_to_end(&mut buffer)
+            .map_err(IdPakLoadError::Io)?;
+
+        Ok(buffer)
+    }
+}
+
+/// Whether an entry name is safe to join onto an extraction
+/// destination, i.e. it is relative and has no `..` components.
+fn is_safe_entry_name(name: &str) -> bool {
+    use std::path::Component;
+
+    let path = Path::new(name);
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
 }
 
 /// Open a PAK file from path and read it's
@@ -142,3 +434,112 @@ where
 pub fn open<P: AsRef<Path>>(path: P) -> IdPakLoadResult<IdPakFile> {
     IdPak::from_path(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IdPakBuilder;
+    use std::io::Cursor;
+
+    fn build_test_pak() -> IdPak<Cursor<Vec<u8>>> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut builder = IdPakBuilder::new(&mut buffer).unwrap();
+        builder.append_bytes("maps/e1m1.bsp", &[1, 2, 3]).unwrap();
+        builder.finish().unwrap();
+
+        buffer.set_position(0);
+        IdPak::new(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_entries_contains_and_entry() {
+        let pak = build_test_pak();
+
+        assert!(pak.contains("maps/e1m1.bsp"));
+        assert!(!pak.contains("maps/e1m2.bsp"));
+
+        let entry = pak.entry("maps/e1m1.bsp").unwrap();
+        assert_eq!(entry.size, 3);
+
+        let names: Vec<&str> = pak.entries().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["maps/e1m1.bsp"]);
+    }
+
+    #[test]
+    fn test_unpack_rejects_unsafe_names() {
+        assert!(is_safe_entry_name("maps/e1m1.bsp"));
+        assert!(!is_safe_entry_name("../e1m1.bsp"));
+        assert!(!is_safe_entry_name("/etc/passwd"));
+    }
+
+    #[test]
+    fn test_unpack_writes_files_to_dest() {
+        let mut pak = build_test_pak();
+        let dest = std::env::temp_dir().join("id_pak_test_unpack_writes_files_to_dest");
+
+        pak.unpack(&dest).unwrap();
+        let contents = std::fs::read(dest.join("maps/e1m1.bsp")).unwrap();
+        assert_eq!(contents, vec![1, 2, 3]);
+
+        std::fs::remove_dir_all(&dest).unwrap();
+    }
+
+    #[test]
+    fn test_unpack_parallel_writes_files_to_dest() {
+        let temp_pak = std::env::temp_dir().join("id_pak_test_unpack_parallel.pak");
+        {
+            let file = File::create(&temp_pak).unwrap();
+            let mut builder = IdPakBuilder::new(file).unwrap();
+            builder.append_bytes("maps/e1m1.bsp", &[1, 2, 3]).unwrap();
+            builder.finish().unwrap();
+        }
+
+        let pak = IdPak::from_path(&temp_pak).unwrap();
+        let dest = std::env::temp_dir().join("id_pak_test_unpack_parallel_dest");
+
+        pak.unpack_parallel(&dest).unwrap();
+        let contents = std::fs::read(dest.join("maps/e1m1.bsp")).unwrap();
+        assert_eq!(contents, vec![1, 2, 3]);
+
+        std::fs::remove_file(&temp_pak).unwrap();
+        std::fs::remove_dir_all(&dest).unwrap();
+    }
+
+    #[test]
+    fn test_unpack_parallel_requires_path() {
+        let temp_pak = std::env::temp_dir().join("id_pak_test_unpack_parallel_requires_path.pak");
+        {
+            let file = File::create(&temp_pak).unwrap();
+            let mut builder = IdPakBuilder::new(file).unwrap();
+            builder.append_bytes("maps/e1m1.bsp", &[1, 2, 3]).unwrap();
+            builder.finish().unwrap();
+        }
+
+        // Opened via `IdPak::new` rather than `from_path`, so no
+        // source path is recorded to reopen per worker.
+        let file = File::open(&temp_pak).unwrap();
+        let pak = IdPak::new(file).unwrap();
+
+        match pak.unpack_parallel(std::env::temp_dir()) {
+            Err(IdPakLoadError::ParallelUnsupported) => (),
+            _ => panic!("Test not passed!"),
+        }
+
+        std::fs::remove_file(&temp_pak).unwrap();
+    }
+
+    #[test]
+    fn test_checksums_round_trip_and_verify() {
+        let mut pak = build_test_pak();
+        let manifest = pak.checksums().unwrap();
+        assert!(pak.verify_against(&manifest).is_ok());
+
+        let mut tampered = manifest.clone();
+        tampered.insert("maps/e1m1.bsp".to_string(), [0u8; 32]);
+
+        match pak.verify_against(&tampered) {
+            Err(IdPakLoadError::ChecksumMismatch(name)) => assert_eq!(name, "maps/e1m1.bsp"),
+            _ => panic!("Test not passed!"),
+        }
+    }
+}